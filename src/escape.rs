@@ -0,0 +1,84 @@
+use std::fmt;
+
+/// A pluggable output-escaping strategy.
+///
+/// `TemplateBuilder::write_str` runs every `:`-rendered string through the
+/// active `Escape` implementation before it reaches the output sink.
+/// `write_raw` (and therefore `Raw`) bypasses escaping entirely, regardless
+/// of which `Escape` is in effect.
+pub trait Escape {
+    /// Write `s` to `out`, applying whatever escaping this scheme requires.
+    fn escape(&self, s: &str, out: &mut fmt::Write) -> fmt::Result;
+}
+
+/// Escapes the five characters that are unsafe to leave unescaped in HTML
+/// text or attribute values. This is the default escaper used by `html! {}`.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct HtmlEscape;
+
+impl Escape for HtmlEscape {
+    fn escape(&self, s: &str, out: &mut fmt::Write) -> fmt::Result {
+        for c in s.chars() {
+            match c {
+                '&' => out.write_str("&amp;")?,
+                '<' => out.write_str("&lt;")?,
+                '>' => out.write_str("&gt;")?,
+                '"' => out.write_str("&quot;")?,
+                '\'' => out.write_str("&#x27;")?,
+                c => out.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Escapes the same characters as [`HtmlEscape`]. Kept as a distinct type so
+/// templates embedded in an XML document can opt into an XML-flavored
+/// escaper explicitly, rather than relying on HTML and XML happening to
+/// agree.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct XmlEscape;
+
+impl Escape for XmlEscape {
+    fn escape(&self, s: &str, out: &mut fmt::Write) -> fmt::Result {
+        HtmlEscape.escape(s, out)
+    }
+}
+
+/// Escapes a string for embedding inside a single- or double-quoted
+/// JavaScript string literal. Also escapes `<`, `>`, and `&` as their
+/// `\xNN` hex forms so a `</script>` (or similar) sequence can't break out
+/// of a surrounding `<script>` block if the result is ever embedded there.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct JsEscape;
+
+impl Escape for JsEscape {
+    fn escape(&self, s: &str, out: &mut fmt::Write) -> fmt::Result {
+        for c in s.chars() {
+            match c {
+                '\\' => out.write_str("\\\\")?,
+                '\'' => out.write_str("\\'")?,
+                '"' => out.write_str("\\\"")?,
+                '\n' => out.write_str("\\n")?,
+                '\r' => out.write_str("\\r")?,
+                '<' => out.write_str("\\x3C")?,
+                '>' => out.write_str("\\x3E")?,
+                '&' => out.write_str("\\x26")?,
+                c => out.write_char(c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Writes strings through unchanged. Useful when a template's output is
+/// already known to be safe (or is about to be escaped some other way), and
+/// paying for `HtmlEscape`'s per-character scan would be wasted work.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct NoEscape;
+
+impl Escape for NoEscape {
+    fn escape(&self, s: &str, out: &mut fmt::Write) -> fmt::Result {
+        out.write_str(s)
+    }
+}
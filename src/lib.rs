@@ -0,0 +1,17 @@
+//! `horrorshow` is a macro-based html builder.
+//!
+//! The `html! {}` macro compiles a small templating DSL down to a closure
+//! that writes directly into a [`TemplateBuilder`], so there's no
+//! intermediate tree or parser at runtime. See the crate's README/tests for
+//! macro syntax examples.
+
+#[macro_use]
+mod macros;
+
+mod escape;
+mod render;
+mod template;
+
+pub use escape::{Escape, HtmlEscape, JsEscape, NoEscape, XmlEscape};
+pub use render::*;
+pub use template::*;
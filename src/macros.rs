@@ -0,0 +1,244 @@
+//! The `html! {}` macro and its internals.
+//!
+//! The statement muncher below (`__horrorshow_stmts!`) is a classic
+//! `macro_rules!` tt-muncher: it walks the template body one statement at a
+//! time and, instead of emitting a `write_raw` call per literal fragment,
+//! threads a pending accumulator of adjacent compile-time-constant text
+//! through the recursion. The accumulator is only flushed (as a single
+//! `write_raw(concat!(...))` call) once a dynamic statement is reached or
+//! the input runs out, so a run of body-less sibling elements (`br; hr;`)
+//! compiles down to one `write_raw` instead of one per tag. The end of each
+//! `{ ... }` body also counts as the input running out, so the markup
+//! before and after a body is written separately.
+//!
+//! Note this coalescing only ever touches *structural* markup (angle
+//! brackets, tag names, attribute punctuation) — never a `:` statement's
+//! content, literal or not. Those always go through `RenderOnce`, because
+//! escaping is a property of whichever `Escape` the template ends up
+//! rendered with (see `with_escaper`), which isn't known until render time;
+//! baking "pre-escaped" text into a `&'static str` at macro expansion time
+//! would silently use the wrong escaper whenever a template is rendered
+//! with anything other than the default.
+//!
+//! Hyphenated tag names (`foo-bar { ... }`) are munched one hyphen at a
+//! time by `__horrorshow_tagname!` instead of with a single
+//! `$(- $more:ident)*` repetition: `macro_rules!` can't unambiguously decide
+//! where such a repetition ends when it's immediately followed by another
+//! open-ended `$($rest:tt)*`, so each hyphen segment is peeled off one
+//! recursive step at a time and accumulated into a parenthesized group
+//! instead.
+
+/// Build an HTML fragment at compile time. See the crate's tests for
+/// example syntax.
+#[macro_export]
+macro_rules! html {
+    ($($body:tt)*) => {{
+        static __HORRORSHOW_SIZE_HINT: $crate::SizeHint = $crate::SizeHint::new();
+        // Bound to a local first: passing the closure literal straight to
+        // `__new_sized_renderer` would let its `F: FnOnce(..)` bound pin the
+        // closure's inferred kind to `FnOnce`, even though it never moves
+        // out of its captures — `Renderer<F>`'s `Render`/`RenderMut` impls
+        // need `F: Fn`/`F: FnMut` to still hold.
+        let __horrorshow_renderer = move |tmpl: &mut $crate::TemplateBuilder| {
+            __horrorshow_stmts!(@stmts tmpl () $($body)*);
+        };
+        $crate::__new_sized_renderer(
+            &__HORRORSHOW_SIZE_HINT,
+            __horrorshow_size_stmts!(@size $($body)*),
+            __horrorshow_renderer,
+        )
+    }};
+}
+
+/// Like [`html!`], but trims leading/trailing whitespace off every `:`
+/// statement's rendered text, producing more compact output. Attribute
+/// values are written the same way and are trimmed too; tag names and
+/// attribute punctuation are left as they are. See [`Template::trimmed`].
+#[macro_export]
+macro_rules! html_trim {
+    ($($body:tt)*) => {{
+        $crate::Template::trimmed(html! { $($body)* })
+    }};
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __horrorshow_attrs {
+    ($t:ident ;) => {};
+    ($t:ident ; $key:ident = $val:expr) => {
+        $t.write_raw(concat!(" ", stringify!($key), "=\""));
+        $t.write_fmt(format_args!("{}", $val));
+        $t.write_raw("\"");
+    };
+    ($t:ident ; $key:ident = $val:expr, $($rest:tt)*) => {
+        $t.write_raw(concat!(" ", stringify!($key), "=\""));
+        $t.write_fmt(format_args!("{}", $val));
+        $t.write_raw("\"");
+        __horrorshow_attrs!($t ; $($rest)*);
+    };
+}
+
+// Peels a tag name off the front of the input, one `- ident` segment at a
+// time, then hands off to `__horrorshow_tagbody!` once the name is known in
+// full (either followed by `(attrs)` or not).
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __horrorshow_tagname {
+    (@tagname $t:ident ($($acc:expr),*) $first:ident ($($more:ident)*) - $next:ident $($rest:tt)*) => {
+        __horrorshow_tagname!(@tagname $t ($($acc),*) $first ($($more)* $next) $($rest)*);
+    };
+    (@tagname $t:ident ($($acc:expr),*) $first:ident ($($more:ident)*) ( $($attr:tt)* ) $($rest:tt)*) => {
+        $t.write_raw(concat!($($acc),*));
+        $t.write_raw(concat!("<", stringify!($first) $(, "-", stringify!($more))*));
+        __horrorshow_attrs!($t ; $($attr)*);
+        $t.write_raw(">");
+        __horrorshow_tagbody!(@tagbody $t () $first ($($more)*) $($rest)*);
+    };
+    (@tagname $t:ident ($($acc:expr),*) $first:ident ($($more:ident)*) $($rest:tt)*) => {
+        __horrorshow_tagbody!(@tagbody $t ($($acc,)* concat!("<", stringify!($first) $(, "-", stringify!($more))*, ">")) $first ($($more)*) $($rest)*);
+    };
+}
+
+// Finishes a tag once its opening markup (with or without attributes) has
+// already been written or queued: `$first`/`$more` are the tag name pieces,
+// still needed to build the `</tag>` closing text.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __horrorshow_tagbody {
+    (@tagbody $t:ident ($($acc:expr),*) $first:ident ($($more:ident)*) { $($body:tt)* } $($rest:tt)*) => {
+        __horrorshow_stmts!(@stmts $t ($($acc),*) $($body)*);
+        __horrorshow_stmts!(@stmts $t (concat!("</", stringify!($first) $(, "-", stringify!($more))*, ">")) $($rest)*);
+    };
+    (@tagbody $t:ident ($($acc:expr),*) $first:ident ($($more:ident)*) : $e:expr ; $($rest:tt)*) => {
+        $t.write_raw(concat!($($acc),*));
+        $crate::RenderOnce::render_once($e, $t);
+        __horrorshow_stmts!(@stmts $t (concat!("</", stringify!($first) $(, "-", stringify!($more))*, ">")) $($rest)*);
+    };
+    (@tagbody $t:ident ($($acc:expr),*) $first:ident ($($more:ident)*) : $e:expr) => {
+        $t.write_raw(concat!($($acc),*));
+        $crate::RenderOnce::render_once($e, $t);
+        $t.write_raw(concat!("</", stringify!($first) $(, "-", stringify!($more))*, ">"));
+    };
+    (@tagbody $t:ident ($($acc:expr),*) $first:ident ($($more:ident)*) ; $($rest:tt)*) => {
+        __horrorshow_stmts!(@stmts $t ($($acc,)* concat!("</", stringify!($first) $(, "-", stringify!($more))*, ">")) $($rest)*);
+    };
+    (@tagbody $t:ident ($($acc:expr),*) $first:ident ($($more:ident)*)) => {
+        $t.write_raw(concat!($($acc,)* concat!("</", stringify!($first) $(, "-", stringify!($more))*, ">")));
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __horrorshow_stmts {
+    // End of input: flush whatever static text is still pending.
+    (@stmts $t:ident ($($acc:expr),*)) => {
+        $t.write_raw(concat!($($acc),*));
+    };
+
+    // A bare `;` is just a statement separator.
+    (@stmts $t:ident ($($acc:expr),*) ; $($rest:tt)*) => {
+        __horrorshow_stmts!(@stmts $t ($($acc),*) $($rest)*);
+    };
+
+    // `: expr;` / `: expr` — render an arbitrary `RenderOnce` (literal text,
+    // numbers, `format_args!`, nested templates, ...).
+    (@stmts $t:ident ($($acc:expr),*) : $e:expr ; $($rest:tt)*) => {
+        $t.write_raw(concat!($($acc),*));
+        $crate::RenderOnce::render_once($e, $t);
+        __horrorshow_stmts!(@stmts $t () $($rest)*);
+    };
+    (@stmts $t:ident ($($acc:expr),*) : $e:expr) => {
+        $t.write_raw(concat!($($acc),*));
+        $crate::RenderOnce::render_once($e, $t);
+    };
+
+    // `|tmpl| { ... }` — drop to the raw `TemplateBuilder` API.
+    (@stmts $t:ident ($($acc:expr),*) | $p:pat | $body:block $($rest:tt)*) => {
+        $t.write_raw(concat!($($acc),*));
+        (|$p: &mut $crate::TemplateBuilder| $body)($t);
+        __horrorshow_stmts!(@stmts $t () $($rest)*);
+    };
+    // `|tmpl| expr;` — same, for a single expression statement.
+    (@stmts $t:ident ($($acc:expr),*) | $p:pat | $body:expr ; $($rest:tt)*) => {
+        $t.write_raw(concat!($($acc),*));
+        (|$p: &mut $crate::TemplateBuilder| { $body; })($t);
+        __horrorshow_stmts!(@stmts $t () $($rest)*);
+    };
+
+    // `tag ...` / `tag-with-hyphens ...` — hand off to the tag-name muncher,
+    // which will itself dispatch on whether `(attrs)` follow.
+    (@stmts $t:ident ($($acc:expr),*) $first:ident $($rest:tt)*) => {
+        __horrorshow_tagname!(@tagname $t ($($acc),*) $first () $($rest)*);
+    };
+}
+
+// `expected_size` companions to `__horrorshow_stmts!`/`__horrorshow_tagname!`/
+// `__horrorshow_tagbody!`: instead of threading a `TemplateBuilder` and
+// emitting `write_raw`/`render_once` calls, these sum up the byte length of
+// whatever structural markup is known at compile time (tag punctuation, tag
+// names, `</tag>` closers), folding each dynamic `:`/`|tmpl|` statement's
+// contribution in as `0` since it isn't known until render time. The result
+// is only ever used as a pre-allocation hint, so undercounting attribute
+// punctuation (not summed below) is acceptable.
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __horrorshow_size_stmts {
+    (@size) => { 0usize };
+    (@size ; $($rest:tt)*) => {
+        __horrorshow_size_stmts!(@size $($rest)*)
+    };
+    (@size : $e:expr ; $($rest:tt)*) => {
+        __horrorshow_size_stmts!(@size $($rest)*)
+    };
+    (@size : $e:expr) => { 0usize };
+    (@size | $p:pat | $body:block $($rest:tt)*) => {
+        __horrorshow_size_stmts!(@size $($rest)*)
+    };
+    (@size | $p:pat | $body:expr ; $($rest:tt)*) => {
+        __horrorshow_size_stmts!(@size $($rest)*)
+    };
+    (@size $first:ident $($rest:tt)*) => {
+        __horrorshow_size_tagname!(@size_tagname $first () $($rest)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __horrorshow_size_tagname {
+    (@size_tagname $first:ident ($($more:ident)*) - $next:ident $($rest:tt)*) => {
+        __horrorshow_size_tagname!(@size_tagname $first ($($more)* $next) $($rest)*)
+    };
+    (@size_tagname $first:ident ($($more:ident)*) ( $($attr:tt)* ) $($rest:tt)*) => {
+        concat!("<", stringify!($first) $(, "-", stringify!($more))*, ">").len()
+            + __horrorshow_size_tagbody!(@size_tagbody $first ($($more)*) $($rest)*)
+    };
+    (@size_tagname $first:ident ($($more:ident)*) $($rest:tt)*) => {
+        concat!("<", stringify!($first) $(, "-", stringify!($more))*, ">").len()
+            + __horrorshow_size_tagbody!(@size_tagbody $first ($($more)*) $($rest)*)
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __horrorshow_size_tagbody {
+    (@size_tagbody $first:ident ($($more:ident)*) { $($body:tt)* } $($rest:tt)*) => {
+        __horrorshow_size_stmts!(@size $($body)*)
+            + concat!("</", stringify!($first) $(, "-", stringify!($more))*, ">").len()
+            + __horrorshow_size_stmts!(@size $($rest)*)
+    };
+    (@size_tagbody $first:ident ($($more:ident)*) : $e:expr ; $($rest:tt)*) => {
+        concat!("</", stringify!($first) $(, "-", stringify!($more))*, ">").len()
+            + __horrorshow_size_stmts!(@size $($rest)*)
+    };
+    (@size_tagbody $first:ident ($($more:ident)*) : $e:expr) => {
+        concat!("</", stringify!($first) $(, "-", stringify!($more))*, ">").len()
+    };
+    (@size_tagbody $first:ident ($($more:ident)*) ; $($rest:tt)*) => {
+        concat!("</", stringify!($first) $(, "-", stringify!($more))*, ">").len()
+            + __horrorshow_size_stmts!(@size $($rest)*)
+    };
+    (@size_tagbody $first:ident ($($more:ident)*)) => {
+        concat!("</", stringify!($first) $(, "-", stringify!($more))*, ">").len()
+    };
+}
@@ -1,4 +1,8 @@
+use std::borrow::Cow;
 use std::fmt;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
 
 use template::{TemplateBuilder, Template};
 
@@ -131,33 +135,121 @@ impl<'b> Render for Box<Render + 'b> {
     }
 }
 
+// {{{ Size hints
+
+/// A learned, self-tuning estimate of a template's rendered size.
+///
+/// `html! {}` gives every template a static `expected_size` computed from
+/// its tag markup (`:` content counts as zero), but a template's actual output (after loops,
+/// conditionals, and interpolated values) is often much larger. A
+/// `SizeHint` remembers the size of the last few renders (as an
+/// exponential moving average) so later renders can pre-allocate close to
+/// the real size instead of the size of the static markup. Reads and
+/// writes use relaxed atomics, so concurrent renders of the same template
+/// never block on each other; the estimate is a heuristic, not a
+/// guarantee.
+pub struct SizeHint(AtomicUsize);
+
+impl SizeHint {
+    /// Create a new, unwarmed size hint.
+    pub const fn new() -> SizeHint {
+        SizeHint(AtomicUsize::new(0))
+    }
+
+    /// Return a capacity to pre-allocate: the learned estimate plus ~12%
+    /// headroom, or `default` if this hint hasn't seen a render yet.
+    pub fn get(&self, default: usize) -> usize {
+        let base = self.0.load(Ordering::Relaxed);
+        if base == 0 {
+            default
+        } else {
+            base + base / 8 + 16
+        }
+    }
+
+    /// Blend `actual` (the size of the render that just completed) into
+    /// the running estimate.
+    pub fn update(&self, actual: usize) {
+        let old = self.0.load(Ordering::Relaxed);
+        let new = if old == 0 {
+            actual
+        } else {
+            (old * 7 + actual * 9 / 8).div_ceil(8)
+        };
+        self.0.store(new, Ordering::Relaxed);
+    }
+}
+
+impl Default for SizeHint {
+    fn default() -> SizeHint {
+        SizeHint::new()
+    }
+}
+
+// }}}
+
 // {{{ Renderer
 
 /// A template renderer. The `html! {}` macro returns a `Renderer`.
 pub struct Renderer<F> {
     renderer: F,
     expected_size: usize,
+    size_hint: Option<&'static SizeHint>,
 }
 
 impl<F> RenderOnce for Renderer<F> where F: FnOnce(&mut TemplateBuilder) {
     fn render_once(self, tmpl: &mut TemplateBuilder) {
-        (self.renderer)(tmpl)
+        match self.size_hint {
+            Some(hint) => {
+                let before = tmpl.len();
+                (self.renderer)(tmpl);
+                // Only a complete render's length is a trustworthy sample: a
+                // render that failed partway through stopped incrementing
+                // `len` when it did, so feeding that truncated length into
+                // the hint would bias future pre-allocations low.
+                if tmpl.result_is_ok() {
+                    hint.update(tmpl.len() - before);
+                }
+            }
+            None => (self.renderer)(tmpl),
+        }
     }
 
     fn size_hint(&self) -> usize {
-        self.expected_size
+        match self.size_hint {
+            Some(hint) => hint.get(self.expected_size),
+            None => self.expected_size,
+        }
     }
 }
 
 impl<F> RenderMut for Renderer<F> where F: FnMut(&mut TemplateBuilder) {
     fn render_mut(&mut self, tmpl: &mut TemplateBuilder) {
-        (self.renderer)(tmpl)
+        match self.size_hint {
+            Some(hint) => {
+                let before = tmpl.len();
+                (self.renderer)(tmpl);
+                if tmpl.result_is_ok() {
+                    hint.update(tmpl.len() - before);
+                }
+            }
+            None => (self.renderer)(tmpl),
+        }
     }
 }
 
 impl<F> Render for Renderer<F> where F: Fn(&mut TemplateBuilder) {
     fn render(&self, tmpl: &mut TemplateBuilder) {
-        (self.renderer)(tmpl)
+        match self.size_hint {
+            Some(hint) => {
+                let before = tmpl.len();
+                (self.renderer)(tmpl);
+                if tmpl.result_is_ok() {
+                    hint.update(tmpl.len() - before);
+                }
+            }
+            None => (self.renderer)(tmpl),
+        }
     }
 }
 
@@ -185,6 +277,7 @@ pub fn __new_renderer<F: FnOnce(&mut TemplateBuilder)>(expected_size: usize, f:
     Renderer {
         renderer: f,
         expected_size: expected_size,
+        size_hint: None,
     }
 }
 
@@ -194,6 +287,37 @@ pub fn __new_boxed_renderer<F: FnOnce(&mut TemplateBuilder)>(expected_size: usiz
     Box::new(Renderer {
         renderer: f,
         expected_size: expected_size,
+        size_hint: None,
+    })
+}
+
+/// Used by the `html! {}` macro. Like `__new_renderer`, but `hint` is a
+/// `static SizeHint` unique to the macro's call site, so repeated renders
+/// of the same template converge on its true output size.
+#[doc(hidden)]
+pub fn __new_sized_renderer<F: FnOnce(&mut TemplateBuilder)>(
+    hint: &'static SizeHint,
+    expected_size: usize,
+    f: F,
+) -> Renderer<F> {
+    Renderer {
+        renderer: f,
+        expected_size: expected_size,
+        size_hint: Some(hint),
+    }
+}
+
+/// Used by the `html! {}` macro. Boxed counterpart of `__new_sized_renderer`.
+#[doc(hidden)]
+pub fn __new_boxed_sized_renderer<F: FnOnce(&mut TemplateBuilder)>(
+    hint: &'static SizeHint,
+    expected_size: usize,
+    f: F,
+) -> Box<Renderer<F>> {
+    Box::new(Renderer {
+        renderer: f,
+        expected_size: expected_size,
+        size_hint: Some(hint),
     })
 }
 
@@ -202,7 +326,7 @@ pub fn __new_boxed_renderer<F: FnOnce(&mut TemplateBuilder)>(expected_size: usiz
 /// Raw content marker.
 ///
 /// When rendered, raw content will not be escaped.
-pub struct Raw<S: AsRef<str>>(S);
+pub struct Raw<S: AsRef<str>>(pub S);
 
 impl<S> Raw<S> where S: AsRef<str> {
     /// Mark as raw.
@@ -283,3 +407,183 @@ impl Render for String {
     }
 }
 
+// fmt::Arguments: lets `: format_args!(...)` be used directly as a `:`
+// statement without an intermediate allocation.
+
+impl<'a> RenderOnce for fmt::Arguments<'a> {
+    #[inline]
+    fn render_once(self, tmpl: &mut TemplateBuilder) {
+        tmpl.write_fmt(self)
+    }
+}
+
+impl<'a> RenderMut for fmt::Arguments<'a> {
+    #[inline]
+    fn render_mut(&mut self, tmpl: &mut TemplateBuilder) {
+        tmpl.write_fmt(*self)
+    }
+}
+
+impl<'a> Render for fmt::Arguments<'a> {
+    #[inline]
+    fn render(&self, tmpl: &mut TemplateBuilder) {
+        tmpl.write_fmt(*self)
+    }
+}
+
+// Primitive, `Display`-based types: written through `write_fmt` so `: 1i32`
+// and friends work directly as `:` statements, without requiring callers to
+// wrap every number in `format_args!` themselves.
+
+macro_rules! display_render_impls {
+    ($($ty:ty),* $(,)*) => {
+        $(
+            impl RenderOnce for $ty {
+                #[inline]
+                fn render_once(self, tmpl: &mut TemplateBuilder) {
+                    tmpl.write_fmt(format_args!("{}", self))
+                }
+            }
+
+            impl RenderMut for $ty {
+                #[inline]
+                fn render_mut(&mut self, tmpl: &mut TemplateBuilder) {
+                    tmpl.write_fmt(format_args!("{}", self))
+                }
+            }
+
+            impl Render for $ty {
+                #[inline]
+                fn render(&self, tmpl: &mut TemplateBuilder) {
+                    tmpl.write_fmt(format_args!("{}", self))
+                }
+            }
+        )*
+    };
+}
+
+display_render_impls!(
+    bool, char, f32, f64, i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize
+);
+
+// Option: render the wrapped value, or nothing for `None`.
+
+impl<T: RenderOnce> RenderOnce for Option<T> {
+    fn render_once(self, tmpl: &mut TemplateBuilder) {
+        if let Some(inner) = self {
+            inner.render_once(tmpl)
+        }
+    }
+    fn size_hint(&self) -> usize {
+        match *self {
+            Some(ref inner) => inner.size_hint(),
+            None => 0,
+        }
+    }
+}
+
+impl<T: RenderMut> RenderMut for Option<T> {
+    fn render_mut(&mut self, tmpl: &mut TemplateBuilder) {
+        if let Some(ref mut inner) = *self {
+            inner.render_mut(tmpl)
+        }
+    }
+}
+
+impl<T: Render> Render for Option<T> {
+    fn render(&self, tmpl: &mut TemplateBuilder) {
+        if let Some(ref inner) = *self {
+            inner.render(tmpl)
+        }
+    }
+}
+
+// Cow<str>
+
+impl<'a> RenderOnce for Cow<'a, str> {
+    #[inline]
+    fn render_once(self, tmpl: &mut TemplateBuilder) {
+        tmpl.write_str(&self)
+    }
+    #[inline]
+    fn size_hint(&self) -> usize {
+        self.len()
+    }
+}
+
+impl<'a> RenderMut for Cow<'a, str> {
+    #[inline]
+    fn render_mut(&mut self, tmpl: &mut TemplateBuilder) {
+        tmpl.write_str(self)
+    }
+}
+
+impl<'a> Render for Cow<'a, str> {
+    #[inline]
+    fn render(&self, tmpl: &mut TemplateBuilder) {
+        tmpl.write_str(self)
+    }
+}
+
+// Rc/Arc: these only ever hand out shared references to their contents, so
+// they can only be rendered through `Render`, regardless of how they're
+// themselves consumed.
+
+macro_rules! shared_ptr_impls {
+    ($ptr:ident) => {
+        impl<T> RenderOnce for $ptr<T> where T: Render {
+            #[inline]
+            fn render_once(self, tmpl: &mut TemplateBuilder) {
+                Render::render(&*self, tmpl)
+            }
+            #[inline]
+            fn size_hint(&self) -> usize {
+                Render::size_hint(&**self)
+            }
+        }
+
+        impl<T> RenderMut for $ptr<T> where T: Render {
+            #[inline]
+            fn render_mut(&mut self, tmpl: &mut TemplateBuilder) {
+                Render::render(&**self, tmpl)
+            }
+        }
+
+        impl<T> Render for $ptr<T> where T: Render {
+            #[inline]
+            fn render(&self, tmpl: &mut TemplateBuilder) {
+                Render::render(&**self, tmpl)
+            }
+        }
+    };
+}
+
+shared_ptr_impls!(Rc);
+shared_ptr_impls!(Arc);
+
+// Tuples: render each element in order, so a sequence like
+// `(header, body, footer)` composes with `:` directly instead of needing a
+// wrapping closure.
+
+macro_rules! tuple_impls {
+    ($($T:ident . $idx:tt),+) => {
+        impl<$($T: RenderOnce),+> RenderOnce for ($($T,)+) {
+            fn render_once(self, tmpl: &mut TemplateBuilder) {
+                $( self.$idx.render_once(tmpl); )+
+            }
+            fn size_hint(&self) -> usize {
+                0 $( + self.$idx.size_hint() )+
+            }
+        }
+    };
+}
+
+tuple_impls!(T0.0);
+tuple_impls!(T0.0, T1.1);
+tuple_impls!(T0.0, T1.1, T2.2);
+tuple_impls!(T0.0, T1.1, T2.2, T3.3);
+tuple_impls!(T0.0, T1.1, T2.2, T3.3, T4.4);
+tuple_impls!(T0.0, T1.1, T2.2, T3.3, T4.4, T5.5);
+tuple_impls!(T0.0, T1.1, T2.2, T3.3, T4.4, T5.5, T6.6);
+tuple_impls!(T0.0, T1.1, T2.2, T3.3, T4.4, T5.5, T6.6, T7.7);
+
@@ -0,0 +1,293 @@
+use std::fmt;
+use std::io;
+use std::mem;
+use std::ops::Shl;
+
+use escape::{Escape, HtmlEscape};
+use render::RenderOnce;
+
+/// The `html! {}` macro builds templates by repeatedly calling methods on a
+/// `TemplateBuilder`. You shouldn't need to touch this type directly unless
+/// you're implementing `Render`/`RenderMut`/`RenderOnce` by hand.
+pub struct TemplateBuilder<'a> {
+    writer: &'a mut fmt::Write,
+    escaper: &'a Escape,
+    result: fmt::Result,
+    len: usize,
+    trim: bool,
+    // When `trim` is set, `write_str`/`write_fmt` don't write through
+    // immediately — they append here instead, so a whole `:` statement's
+    // output (which may arrive as several calls, e.g. one per tuple
+    // element) accumulates before it's trimmed as a single unit. Flushed by
+    // `flush_trim_buffer`, which any write that isn't itself trimmed
+    // (`write_raw`) or the end of rendering must call first.
+    trim_buffer: String,
+}
+
+impl<'a> TemplateBuilder<'a> {
+    /// Write a string, escaping it with the builder's active `Escape`. When
+    /// the template is rendering in trimmed mode (see
+    /// [`Template::trimmed`]), the text is buffered instead, so that a whole
+    /// `:` statement's output trims as one unit rather than each underlying
+    /// write trimming independently (see [`flush_trim_buffer`](Self::flush_trim_buffer)).
+    pub fn write_str(&mut self, text: &str) {
+        if self.result.is_ok() {
+            if self.trim {
+                self.trim_buffer.push_str(text);
+            } else {
+                self.len += text.len();
+                self.result = self.escaper.escape(text, &mut *self.writer);
+            }
+        }
+    }
+
+    /// Write a string without escaping it. Used by `Raw`. Structural markup
+    /// like this always ends whatever `:` statement's text was being
+    /// buffered for trimming, so it flushes the trim buffer first.
+    pub fn write_raw(&mut self, text: &str) {
+        self.flush_trim_buffer();
+        if self.result.is_ok() {
+            self.len += text.len();
+            self.result = self.writer.write_str(text);
+        }
+    }
+
+    /// Write the result of `format_args!`, escaping it with the builder's
+    /// active `Escape`.
+    pub fn write_fmt(&mut self, args: fmt::Arguments) {
+        if self.result.is_ok() {
+            if self.trim {
+                // Buffer alongside any adjacent `write_str` output (a
+                // `fmt::Write` impl may also see this split across several
+                // calls) so the whole run trims together.
+                return self.write_str(&fmt::format(args));
+            }
+
+            struct Adapter<'a, 'b: 'a> {
+                writer: &'a mut (fmt::Write + 'b),
+                escaper: &'a (Escape + 'b),
+                len: &'a mut usize,
+            }
+            impl<'a, 'b> fmt::Write for Adapter<'a, 'b> {
+                fn write_str(&mut self, s: &str) -> fmt::Result {
+                    *self.len += s.len();
+                    self.escaper.escape(s, self.writer)
+                }
+            }
+            self.result = fmt::Write::write_fmt(
+                &mut Adapter {
+                    writer: &mut *self.writer,
+                    escaper: self.escaper,
+                    len: &mut self.len,
+                },
+                args,
+            );
+        }
+    }
+
+    /// Trim and escape whatever text is pending in the trim buffer, then
+    /// write it through. A no-op if nothing (or nothing but whitespace) is
+    /// buffered. Must be called before any write that bypasses the trim
+    /// buffer, and once more after the last write of a trimmed render.
+    fn flush_trim_buffer(&mut self) {
+        if self.trim_buffer.is_empty() {
+            return;
+        }
+        let text = mem::take(&mut self.trim_buffer);
+        if self.result.is_ok() {
+            let trimmed = text.trim();
+            self.len += trimmed.len();
+            self.result = self.escaper.escape(trimmed, &mut *self.writer);
+        }
+    }
+
+    /// The number of (pre-escaping) bytes written through this builder so
+    /// far. Used by `Renderer` to learn a template's real output size.
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether every write through this builder has succeeded so far. Used
+    /// by `Renderer` to avoid training `SizeHint` on a partial render.
+    pub(crate) fn result_is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+
+    /// Build a fresh builder over the same writer, for wrapper types
+    /// (`WithEscaper`, `Trimmed`) that render an inner template with one
+    /// field overridden. Pair with [`absorb`](Self::absorb) to propagate the
+    /// nested builder's result/len back once the inner template has run.
+    fn nested<'b>(&'b mut self, escaper: &'b Escape, trim: bool) -> TemplateBuilder<'b> {
+        // The nested builder writes straight through `writer`, bypassing this
+        // builder's trim buffer, so whatever is pending there goes first.
+        self.flush_trim_buffer();
+        TemplateBuilder {
+            writer: &mut *self.writer,
+            escaper: escaper,
+            result: Ok(()),
+            len: 0,
+            trim: trim,
+            trim_buffer: String::new(),
+        }
+    }
+
+    /// Propagate a [`nested`](Self::nested) builder's outcome back into this
+    /// one after it's done rendering. Callers must flush the nested
+    /// builder's trim buffer first — it's about to go out of scope, and
+    /// with it any text still sitting in `trim_buffer` unflushed.
+    fn absorb(&mut self, result: fmt::Result, len: usize) {
+        self.result = result;
+        self.len += len;
+    }
+}
+
+/// Lets a `|tmpl| ...` block in `html! {}` write values with `tmpl << value`
+/// instead of spelling out `RenderOnce::render_once(value, tmpl)`.
+impl<'a, 'b, T: RenderOnce> Shl<T> for &'b mut TemplateBuilder<'a> {
+    type Output = &'b mut TemplateBuilder<'a>;
+
+    fn shl(self, value: T) -> &'b mut TemplateBuilder<'a> {
+        value.render_once(self);
+        self
+    }
+}
+
+/// A complete template, ready to be rendered to a `String` or any
+/// `fmt::Write` or `io::Write` sink. (`html! {}` renderers can also be
+/// formatted with `{}`: `Renderer` implements `Display`.)
+pub trait Template: RenderOnce {
+    /// Render this template, writing escaped output through `writer`.
+    fn write_to_fmt(self, writer: &mut fmt::Write) -> fmt::Result;
+
+    /// Render this template into a freshly allocated `String`.
+    fn into_string(self) -> Result<String, fmt::Error>;
+
+    /// Render this template straight to an `io::Write` sink (a socket, a
+    /// file, ...) a chunk at a time, instead of buffering the whole
+    /// document in a `String` first. I/O errors from `writer` propagate
+    /// out as-is.
+    fn write_to<W: io::Write>(self, writer: W) -> io::Result<()>
+    where
+        Self: Sized,
+    {
+        struct IoWriter<W> {
+            writer: W,
+            error: Option<io::Error>,
+        }
+        impl<W: io::Write> fmt::Write for IoWriter<W> {
+            fn write_str(&mut self, s: &str) -> fmt::Result {
+                match self.writer.write_all(s.as_bytes()) {
+                    Ok(()) => Ok(()),
+                    Err(err) => {
+                        self.error = Some(err);
+                        Err(fmt::Error)
+                    }
+                }
+            }
+        }
+
+        let mut writer = IoWriter {
+            writer: writer,
+            error: None,
+        };
+        match self.write_to_fmt(&mut writer) {
+            Ok(()) => Ok(()),
+            Err(_) => Err(writer
+                .error
+                .unwrap_or_else(|| io::Error::other("formatter error"))),
+        }
+    }
+
+    /// Render this template using `escaper` instead of the default
+    /// `HtmlEscape`, so the same `html! {}` body can be reused to emit XML,
+    /// a JavaScript string literal, or plain unescaped text.
+    fn with_escaper<E: Escape>(self, escaper: E) -> WithEscaper<Self, E>
+    where
+        Self: Sized,
+    {
+        WithEscaper {
+            inner: self,
+            escaper: escaper,
+        }
+    }
+
+    /// Render this template with every `:` statement's text trimmed of
+    /// leading/trailing whitespace, for more compact output. Used by
+    /// [`html_trim!`].
+    fn trimmed(self) -> Trimmed<Self>
+    where
+        Self: Sized,
+    {
+        Trimmed { inner: self }
+    }
+}
+
+impl<T: RenderOnce> Template for T {
+    fn write_to_fmt(self, writer: &mut fmt::Write) -> fmt::Result {
+        let mut tmpl = TemplateBuilder {
+            writer: writer,
+            escaper: &HtmlEscape,
+            result: Ok(()),
+            len: 0,
+            trim: false,
+            trim_buffer: String::new(),
+        };
+        self.render_once(&mut tmpl);
+        tmpl.flush_trim_buffer();
+        tmpl.result
+    }
+
+    fn into_string(self) -> Result<String, fmt::Error> {
+        let mut string = String::with_capacity(self.size_hint());
+        self.write_to_fmt(&mut string)?;
+        Ok(string)
+    }
+}
+
+/// Wraps a template so it renders with a custom [`Escape`] instead of the
+/// default `HtmlEscape`. Returned by [`Template::with_escaper`].
+pub struct WithEscaper<T, E> {
+    inner: T,
+    escaper: E,
+}
+
+impl<T: RenderOnce, E: Escape> RenderOnce for WithEscaper<T, E> {
+    fn render_once(self, tmpl: &mut TemplateBuilder) {
+        if tmpl.result.is_ok() {
+            let trim = tmpl.trim;
+            let mut nested = tmpl.nested(&self.escaper, trim);
+            self.inner.render_once(&mut nested);
+            nested.flush_trim_buffer();
+            let (result, len) = (nested.result, nested.len);
+            tmpl.absorb(result, len);
+        }
+    }
+
+    fn size_hint(&self) -> usize {
+        self.inner.size_hint()
+    }
+}
+
+/// Wraps a template so every `:` statement's text is trimmed of
+/// leading/trailing whitespace when rendered. Returned by
+/// [`Template::trimmed`] and used by [`html_trim!`].
+pub struct Trimmed<T> {
+    inner: T,
+}
+
+impl<T: RenderOnce> RenderOnce for Trimmed<T> {
+    fn render_once(self, tmpl: &mut TemplateBuilder) {
+        if tmpl.result.is_ok() {
+            let escaper = tmpl.escaper;
+            let mut nested = tmpl.nested(escaper, true);
+            self.inner.render_once(&mut nested);
+            nested.flush_trim_buffer();
+            let (result, len) = (nested.result, nested.len);
+            tmpl.absorb(result, len);
+        }
+    }
+
+    fn size_hint(&self) -> usize {
+        self.inner.size_hint()
+    }
+}
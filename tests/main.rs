@@ -4,7 +4,11 @@ extern crate alloc;
 #[macro_use]
 extern crate horrorshow;
 
-use horrorshow::{Raw, Template};
+use alloc::borrow::Cow;
+use alloc::rc::Rc;
+use alloc::sync::Arc;
+
+use horrorshow::{Escape, HtmlEscape, JsEscape, NoEscape, Raw, RenderOnce, Template, XmlEscape};
 
 #[test]
 fn test_prim() {
@@ -127,6 +131,17 @@ fn test_embed_twice() {
     assert_eq!(r.into_string().unwrap(), "abcdeabcde");
 }
 
+#[test]
+fn test_write_to() {
+    let mut buf = Vec::new();
+    html! {
+        p : "streamed"
+    }
+    .write_to(&mut buf)
+    .unwrap();
+    assert_eq!(buf, b"<p>streamed</p>");
+}
+
 #[test]
 fn test_display() {
     use alloc::fmt::Write;
@@ -137,3 +152,173 @@ fn test_display() {
     write!(s, "{}", r).unwrap();
     assert_eq!(&s, "test");
 }
+
+#[test]
+fn test_html_trim() {
+    assert_eq!(
+        html_trim! {
+            p {
+                : "  hello  "
+            }
+            p {
+                : "  world  "
+            }
+        }
+        .into_string()
+        .unwrap(),
+        "<p>hello</p><p>world</p>"
+    );
+
+    assert_eq!(
+        html_trim! {
+            div : format_args!("  {}  ", "world")
+        }
+        .into_string()
+        .unwrap(),
+        "<div>world</div>"
+    );
+}
+
+#[test]
+fn test_html_trim_statement_granularity() {
+    // A single `:` statement's output should trim as one unit even when it
+    // arrives as several underlying writes (here, one per tuple element) —
+    // trimming each element independently would eat the whitespace between
+    // them along with the leading/trailing whitespace.
+    assert_eq!(
+        html_trim! {
+            : ("  a  ", "  -  ", "  b  ")
+        }
+        .into_string()
+        .unwrap(),
+        "a    -    b"
+    );
+}
+
+#[test]
+fn test_html_escape() {
+    let mut out = String::new();
+    HtmlEscape.escape("<a href=\"x\">'&'</a>", &mut out).unwrap();
+    assert_eq!(out, "&lt;a href=&quot;x&quot;&gt;&#x27;&amp;&#x27;&lt;/a&gt;");
+}
+
+#[test]
+fn test_xml_escape() {
+    let mut out = String::new();
+    XmlEscape.escape("<a href=\"x\">'&'</a>", &mut out).unwrap();
+    assert_eq!(out, "&lt;a href=&quot;x&quot;&gt;&#x27;&amp;&#x27;&lt;/a&gt;");
+}
+
+#[test]
+fn test_js_escape() {
+    // `<`/`>`/`&` must come out as `\xNN` escapes, not literal angle
+    // brackets, so a `</script>` embedded in escaped text can't break out
+    // of a surrounding `<script>` block.
+    let mut out = String::new();
+    JsEscape
+        .escape("</script>\n\r\\'\"", &mut out)
+        .unwrap();
+    assert_eq!(out, "\\x3C/script\\x3E\\n\\r\\\\\\'\\\"");
+}
+
+#[test]
+fn test_no_escape() {
+    let mut out = String::new();
+    NoEscape.escape("<a href=\"x\">'&'</a>", &mut out).unwrap();
+    assert_eq!(out, "<a href=\"x\">'&'</a>");
+}
+
+#[test]
+fn test_with_escaper() {
+    assert_eq!(
+        html! {
+            : "</script>"
+        }
+        .with_escaper(JsEscape)
+        .into_string()
+        .unwrap(),
+        "\\x3C/script\\x3E"
+    );
+}
+
+#[test]
+fn test_size_hint_adapts() {
+    // Each iteration re-expands the same `html! {}` call site, so all
+    // iterations share one `SizeHint`. The first render is unwarmed and
+    // falls back to the static `expected_size` ("<p></p>"'s known length);
+    // after it completes, the hint has learned the real (much larger)
+    // output size, so the second render's estimate should have moved
+    // toward it.
+    let text = "a".repeat(64);
+    let mut hints = Vec::new();
+    for _ in 0..2 {
+        let chunk = text.clone();
+        let r = html! {
+            p : Raw(chunk)
+        };
+        hints.push(r.size_hint());
+        assert_eq!(r.into_string().unwrap().len(), 64 + "<p></p>".len());
+    }
+    assert_eq!(hints[0], "<p></p>".len());
+    assert!(hints[1] > hints[0]);
+}
+
+#[test]
+fn test_expected_size_from_static_markup() {
+    // The unwarmed `SizeHint` falls back to `expected_size`, which `html!`
+    // computes from the template's static markup alone (tag punctuation and
+    // names), not the dynamic content rendered through it; attribute values
+    // aren't counted either, since they're not known until render time.
+    let r = html! {
+        div(id="x") {
+            p : "hello";
+            br;
+        }
+    };
+    assert_eq!(
+        r.size_hint(),
+        "<div>".len() + "<p>".len() + "</p>".len() + "<br>".len() + "</br>".len() + "</div>".len()
+    );
+}
+
+#[test]
+fn test_cow() {
+    let borrowed: Cow<str> = Cow::Borrowed("borrowed");
+    let owned: Cow<str> = Cow::Owned(String::from("owned"));
+    assert_eq!(
+        html! {
+            p : borrowed;
+            p : owned
+        }
+        .into_string()
+        .unwrap(),
+        "<p>borrowed</p><p>owned</p>"
+    );
+}
+
+#[test]
+fn test_shared_ptr() {
+    let rc = Rc::new("by rc");
+    let arc = Arc::new("by arc");
+    assert_eq!(
+        html! {
+            p : &rc;
+            p : &arc
+        }
+        .into_string()
+        .unwrap(),
+        "<p>by rc</p><p>by arc</p>"
+    );
+}
+
+#[test]
+fn test_tuple() {
+    assert_eq!(
+        html! {
+            : ("a", "b", "c")
+        }
+        .into_string()
+        .unwrap(),
+        "abc"
+    );
+}